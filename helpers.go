@@ -1,12 +1,84 @@
 package main
 
 import (
+	"flag"
+	"fmt"
 	"io"
 	"net/http"
 	"strings"
+	"sync"
 	"time"
+	"unicode/utf8"
 )
 
+var (
+	httpMaxConcurrent int
+	httpTimeout       time.Duration
+	httpSemaphore     chan struct{}
+	httpSemaphoreOnce sync.Once
+
+	circuitThreshold    int
+	circuitCooldown     time.Duration
+	circuitBreakers     = make(map[string]*circuitBreaker)
+	circuitBreakersLock sync.Mutex
+)
+
+// circuitBreaker stops hammering a host after circuitThreshold consecutive
+// failures; once circuitCooldown passes a single probe request is let through,
+// and its result either closes the circuit again or restarts the cooldown
+type circuitBreaker struct {
+	lock     sync.Mutex
+	failures int
+	openedAt time.Time
+	probing  bool
+}
+
+func circuitFor(host string) *circuitBreaker {
+	circuitBreakersLock.Lock()
+	defer circuitBreakersLock.Unlock()
+
+	cb, ok := circuitBreakers[host]
+	if !ok {
+		cb = &circuitBreaker{}
+		circuitBreakers[host] = cb
+	}
+
+	return cb
+}
+
+func (cb *circuitBreaker) Allow() bool {
+	cb.lock.Lock()
+	defer cb.lock.Unlock()
+
+	if circuitThreshold <= 0 || cb.failures < circuitThreshold {
+		return true
+	}
+
+	if cb.probing || time.Since(cb.openedAt) < circuitCooldown {
+		return false
+	}
+
+	cb.probing = true
+	return true
+}
+
+func (cb *circuitBreaker) Done(err error) {
+	cb.lock.Lock()
+	defer cb.lock.Unlock()
+
+	cb.probing = false
+
+	if err == nil {
+		cb.failures = 0
+		return
+	}
+
+	cb.failures++
+	if cb.failures >= circuitThreshold {
+		cb.openedAt = time.Now()
+	}
+}
+
 func listSubtract(a, b []string) (ret []string) {
 	mb := make(map[string]bool, len(b))
 
@@ -23,19 +95,50 @@ func listSubtract(a, b []string) (ret []string) {
 	return ret
 }
 
-func httpGet(link string) ([]byte, error) {
-	var buf []byte
+// insert a zero-width space after the first character, so listing someone
+// on IRC doesn't highlight them; splits on a rune, not a byte
+func zwsNick(nick string) string {
+	if len(nick) == 0 {
+		return nick
+	}
+
+	_, size := utf8.DecodeRuneInString(nick)
+	return nick[:size] + "\u200B" + nick[size:]
+}
+
+func httpGet(link string) (buf []byte, err error) {
+	req, err := http.NewRequest("GET", link, nil)
+	if err != nil {
+		return []byte{}, err
+	}
+
+	breaker := circuitFor(req.URL.Host)
+	if !breaker.Allow() {
+		return []byte{}, fmt.Errorf("%s is failing, not retrying until %s cooldown passes", req.URL.Host, circuitCooldown)
+	}
+	defer func() { breaker.Done(err) }()
+
+	// flags are only parsed in main(), so size the semaphore on first use
+	httpSemaphoreOnce.Do(func() {
+		if httpMaxConcurrent > 0 {
+			httpSemaphore = make(chan struct{}, httpMaxConcurrent)
+		}
+	})
+
+	if httpSemaphore != nil {
+		httpSemaphore <- struct{}{}
+		defer func() { <-httpSemaphore }()
+	}
+
 	tr := &http.Transport{
 		TLSHandshakeTimeout:   20 * time.Second,
 		ResponseHeaderTimeout: 20 * time.Second,
 	}
+	// a request stalled mid-body would otherwise block its caller, and hold a
+	// concurrency permit, forever
 	client := &http.Client{
 		Transport: tr,
-	}
-
-	req, err := http.NewRequest("GET", link, nil)
-	if err != nil {
-		return []byte{}, err
+		Timeout:   httpTimeout,
 	}
 
 	resp, err := client.Do(req)
@@ -44,6 +147,11 @@ func httpGet(link string) ([]byte, error) {
 	}
 	defer resp.Body.Close()
 
+	// a dead upstream behind a reverse proxy still answers, with a 502 or 503
+	if resp.StatusCode < 200 || resp.StatusCode > 299 {
+		return []byte{}, fmt.Errorf("%s: unexpected status %s", req.URL.Host, resp.Status)
+	}
+
 	// Limit response to 5MiB
 	limitedResponse := http.MaxBytesReader(nil, resp.Body, 5*1024*1024)
 	buf = make([]byte, 5*1024*1024)
@@ -68,3 +176,10 @@ func (i *arrayFlags) Set(value string) error {
 	*i = append(*i, value)
 	return nil
 }
+
+func init() {
+	flag.IntVar(&httpMaxConcurrent, "http.maxConcurrent", 8, "Maximum number of concurrent outbound HTTP requests; 0 disables the limit")
+	flag.DurationVar(&httpTimeout, "http.timeout", time.Minute, "Overall timeout for each outbound HTTP request")
+	flag.IntVar(&circuitThreshold, "http.circuitThreshold", 5, "Consecutive failures after which requests to a host are short-circuited; 0 disables")
+	flag.DurationVar(&circuitCooldown, "http.circuitCooldown", time.Minute, "How long to short-circuit requests to a failing host before probing it again")
+}
@@ -0,0 +1,199 @@
+package main
+
+import (
+	"errors"
+	"net/http"
+	"net/http/httptest"
+	"sync"
+	"testing"
+	"time"
+)
+
+func TestZwsNick(t *testing.T) {
+	tests := []struct {
+		nick string
+		want string
+	}{
+		{"", ""},
+		{"a", "a\u200B"},
+		{"alice", "a\u200Blice"},
+		{"Łukasz", "Ł\u200Bukasz"},
+		{"ęść", "ę\u200Bść"},
+	}
+
+	for _, tt := range tests {
+		if got := zwsNick(tt.nick); got != tt.want {
+			t.Errorf("zwsNick(%q) = %q, want %q", tt.nick, got, tt.want)
+		}
+	}
+}
+
+func TestCircuitBreaker(t *testing.T) {
+	defer func(threshold int, cooldown time.Duration) {
+		circuitThreshold, circuitCooldown = threshold, cooldown
+	}(circuitThreshold, circuitCooldown)
+	circuitThreshold, circuitCooldown = 3, time.Minute
+
+	failure := errors.New("connection refused")
+	cb := &circuitBreaker{}
+
+	// closed: failures below the threshold still let requests through
+	for i := 0; i < circuitThreshold-1; i++ {
+		if !cb.Allow() {
+			t.Fatalf("closed circuit rejected request after %d failures", i)
+		}
+		cb.Done(failure)
+	}
+
+	// a success resets the failure count
+	if !cb.Allow() {
+		t.Fatal("closed circuit rejected request")
+	}
+	cb.Done(nil)
+	if cb.failures != 0 {
+		t.Fatalf("failures = %d after a success, want 0", cb.failures)
+	}
+
+	// open: threshold consecutive failures short-circuit requests
+	for i := 0; i < circuitThreshold; i++ {
+		cb.Allow()
+		cb.Done(failure)
+	}
+	if cb.Allow() {
+		t.Fatal("open circuit allowed a request before the cooldown passed")
+	}
+
+	// half-open: after the cooldown exactly one probe goes through
+	cb.openedAt = time.Now().Add(-2 * circuitCooldown)
+	if !cb.Allow() {
+		t.Fatal("circuit didn't allow a probe after the cooldown")
+	}
+	if cb.Allow() {
+		t.Fatal("circuit allowed a second request while probing")
+	}
+
+	// a failed probe reopens the circuit for another cooldown
+	cb.Done(failure)
+	if cb.Allow() {
+		t.Fatal("circuit allowed a request right after a failed probe")
+	}
+
+	// a successful probe closes it again
+	cb.openedAt = time.Now().Add(-2 * circuitCooldown)
+	if !cb.Allow() {
+		t.Fatal("circuit didn't allow a probe after the second cooldown")
+	}
+	cb.Done(nil)
+	for i := 0; i < circuitThreshold-1; i++ {
+		if !cb.Allow() {
+			t.Fatal("circuit rejected a request after a successful probe")
+		}
+		cb.Done(failure)
+	}
+}
+
+func TestCircuitBreakerDisabled(t *testing.T) {
+	defer func(threshold int) { circuitThreshold = threshold }(circuitThreshold)
+	circuitThreshold = 0
+
+	cb := &circuitBreaker{}
+	for i := 0; i < 10; i++ {
+		if !cb.Allow() {
+			t.Fatalf("disabled circuit rejected request after %d failures", i)
+		}
+		cb.Done(errors.New("connection refused"))
+	}
+}
+
+func TestHttpGetStatus(t *testing.T) {
+	tests := []struct {
+		status  int
+		wantErr bool
+	}{
+		{http.StatusOK, false},
+		{http.StatusNotFound, true},
+		{http.StatusBadGateway, true},
+		{http.StatusServiceUnavailable, true},
+	}
+
+	for _, tt := range tests {
+		srv := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+			w.WriteHeader(tt.status)
+			w.Write([]byte("{}"))
+		}))
+
+		_, err := httpGet(srv.URL)
+		if (err != nil) != tt.wantErr {
+			t.Errorf("httpGet with status %d: err = %v, want error: %v", tt.status, err, tt.wantErr)
+		}
+
+		srv.Close()
+	}
+}
+
+func TestHttpGetConcurrencyLimit(t *testing.T) {
+	defer func(max int) {
+		httpMaxConcurrent, httpSemaphore, httpSemaphoreOnce = max, nil, sync.Once{}
+	}(httpMaxConcurrent)
+	httpMaxConcurrent, httpSemaphore, httpSemaphoreOnce = 2, nil, sync.Once{}
+
+	var (
+		lock     sync.Mutex
+		inFlight int
+		peak     int
+		wg       sync.WaitGroup
+	)
+	release := make(chan struct{})
+
+	srv := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		lock.Lock()
+		inFlight++
+		if inFlight > peak {
+			peak = inFlight
+		}
+		lock.Unlock()
+
+		<-release
+
+		lock.Lock()
+		inFlight--
+		lock.Unlock()
+
+		w.Write([]byte("{}"))
+	}))
+	defer srv.Close()
+
+	for i := 0; i < httpMaxConcurrent+3; i++ {
+		wg.Add(1)
+		go func() {
+			defer wg.Done()
+			if _, err := httpGet(srv.URL); err != nil {
+				t.Error(err)
+			}
+		}()
+	}
+
+	// wait for the permitted requests to arrive, then give the others a chance
+	// to get past the limit before letting everything through
+	deadline := time.Now().Add(5 * time.Second)
+	for time.Now().Before(deadline) {
+		lock.Lock()
+		n := inFlight
+		lock.Unlock()
+
+		if n >= httpMaxConcurrent {
+			break
+		}
+		time.Sleep(10 * time.Millisecond)
+	}
+	time.Sleep(200 * time.Millisecond)
+
+	close(release)
+	wg.Wait()
+
+	lock.Lock()
+	defer lock.Unlock()
+	if peak != httpMaxConcurrent {
+		t.Errorf("peak concurrent requests = %d, want %d", peak, httpMaxConcurrent)
+	}
+}
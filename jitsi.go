@@ -6,6 +6,7 @@ import (
 	"fmt"
 	"log"
 	"strings"
+	"sync"
 	"time"
 
 	"golang.org/x/net/websocket"
@@ -79,8 +80,7 @@ type JitsiClient struct {
 
 func (j *JitsiClient) UserListZWS() (ret []string) {
 	for _, user := range j.users {
-		u := user[:1] + "\u200B" + user[1:]
-		ret = append(ret, u)
+		ret = append(ret, zwsNick(user))
 	}
 	return ret
 }
@@ -121,6 +121,7 @@ func (j *JitsiClient) Run(c *irc.Client, done chan bool) {
 	j.users = make(map[string]string)
 
 	for {
+		connDone := make(chan bool)
 		log.Println("JitsiClient", j.server, j.room, "Initializing")
 
 		ws, err := websocket.Dial(url, protocol, origin)
@@ -129,6 +130,15 @@ func (j *JitsiClient) Run(c *irc.Client, done chan bool) {
 			goto reconnect
 		}
 
+		// closing the websocket is the only way to interrupt a pending ws.Read
+		go func() {
+			select {
+			case <-done:
+				ws.Close()
+			case <-connDone:
+			}
+		}()
+
 		for n, frame := range initFrames {
 			if _, err := ws.Write([]byte(frame)); err != nil {
 				log.Println("JitsiClient", j.server, j.room, "Error sending initialization frame", n, err)
@@ -149,6 +159,12 @@ func (j *JitsiClient) Run(c *irc.Client, done chan bool) {
 				v := JitsiPresence{}
 
 				if err != nil {
+					select {
+					case <-done:
+						log.Println("JitsiClient", j.server, j.room, "Shutting down")
+						return
+					default:
+					}
 					log.Println("JitsiClient", j.server, j.room, "Error while reading from websocket", err)
 					goto reconnect
 				}
@@ -177,7 +193,7 @@ func (j *JitsiClient) Run(c *irc.Client, done chan bool) {
 								alsoThere = fmt.Sprint(", also there:", previousList)
 							}
 							j.users[v.X.Item.Jid] = v.Nick.Text
-							nickZws := v.Nick.Text[:1] + "\u200B" + v.Nick.Text[1:]
+							nickZws := zwsNick(v.Nick.Text)
 							ircMsg := fmt.Sprintf("NOTICE %s :jitsi: +%s%s\n", j.ircChannel, nickZws, alsoThere)
 							log.Println("JitsiClient", j.server, j.room, "User joined:", j.users[v.X.Item.Jid])
 							c.Write(ircMsg)
@@ -193,7 +209,7 @@ func (j *JitsiClient) Run(c *irc.Client, done chan bool) {
 							if len(userList) > 0 {
 								alsoThere = fmt.Sprint(", still there:", userList)
 							}
-							nickZws := knownNick[:1] + "\u200B" + knownNick[1:]
+							nickZws := zwsNick(knownNick)
 							ircMsg := fmt.Sprintf("NOTICE %s :jitsi: -%s%s\n", j.ircChannel, nickZws, alsoThere)
 							log.Println("JitsiClient", j.server, j.room, "User left:", knownNick)
 							c.Write(ircMsg)
@@ -204,12 +220,23 @@ func (j *JitsiClient) Run(c *irc.Client, done chan bool) {
 			}
 		}
 	reconnect:
-		time.Sleep(1 * time.Second)
+		close(connDone)
+		if ws != nil {
+			ws.Close()
+		}
+
+		select {
+		case <-done:
+			log.Println("JitsiClient", j.server, j.room, "Shutting down")
+			return
+		case <-time.After(1 * time.Second):
+		}
 		log.Println("JitsiClient", j.server, j.room, "Reconnecting...")
 	}
 }
 
 func JitsiRunWrapper(c *irc.Client, done chan bool) {
+	var wg sync.WaitGroup
 	jitsiDone := make([]chan bool, len(jitsiChannels))
 
 	for i, ch := range jitsiChannels {
@@ -225,17 +252,23 @@ func JitsiRunWrapper(c *irc.Client, done chan bool) {
 			room:       args[2],
 		}
 
-		go j.Run(c, jitsiDone[i])
+		jitsiDone[i] = make(chan bool)
+		wg.Add(1)
+		go func(done chan bool) {
+			defer wg.Done()
+			j.Run(c, done)
+		}(jitsiDone[i])
 	}
 
 	<-done
 	for _, ch := range jitsiDone {
-		ch <- true
+		close(ch)
 	}
+	wg.Wait()
 }
 
 func init() {
 	flag.Var(&jitsiChannels, "jitsi.channels", "ircChannel,jitsiServer,jitsiRoom mapping; may be specified multiple times")
 
-	Runners.Add(JitsiRunWrapper)
+	Runners.Add("jitsi", JitsiRunWrapper)
 }
@@ -4,17 +4,24 @@ import (
 	"flag"
 	"log"
 	"net"
+	"os"
+	"os/signal"
+	"sync"
+	"syscall"
+	"time"
 
 	"gopkg.in/irc.v3"
 )
 
 var (
-	channels arrayFlags
-	server   string
-	nickname string
-	password string
-	user     string
-	name     string
+	channels  arrayFlags
+	server    string
+	nickname  string
+	password  string
+	user      string
+	name      string
+	sendLimit time.Duration
+	sendBurst int
 )
 
 func init() {
@@ -24,12 +31,20 @@ func init() {
 	flag.StringVar(&user, "user", "bot", "Bot user parameter")
 	flag.StringVar(&name, "name", "bot notbot", "Bot real name parameter")
 	flag.Var(&channels, "channels", "Channel to join; may be specified multiple times")
+	flag.DurationVar(&sendLimit, "sendLimit", 0, "Minimum delay between messages sent once the burst is used up, to avoid Excess Flood disconnects; 0 disables throttling")
+	flag.IntVar(&sendBurst, "sendBurst", 4, "Number of messages that may be sent at once before -sendLimit throttling kicks in")
 }
 
 func main() {
-	done := make([]chan bool, len(Runners.list))
+	var wg sync.WaitGroup
+	shutdown := make(chan bool)
+	signals := make(chan os.Signal, 1)
 
 	flag.Parse()
+	checkDisabled()
+
+	enabledRunners := Runners.Enabled()
+	done := make([]chan bool, len(enabledRunners))
 
 	conn, err := net.Dial("tcp", server)
 	if err != nil {
@@ -37,24 +52,50 @@ func main() {
 	}
 
 	config := irc.ClientConfig{
-		Nick:    nickname,
-		Pass:    password,
-		User:    user,
-		Name:    name,
-		Handler: irc.HandlerFunc(handlerFactory(Dispatchers.list)),
+		Nick:      nickname,
+		Pass:      password,
+		User:      user,
+		Name:      name,
+		SendLimit: sendLimit,
+		SendBurst: sendBurst,
+		Handler:   irc.HandlerFunc(handlerFactory(Dispatchers.Enabled())),
 	}
 
 	client := irc.NewClient(conn, config)
 
-	for i, runner := range Runners.list {
-		go runner(client, done[i])
+	for i, runner := range enabledRunners {
+		done[i] = make(chan bool)
+		wg.Add(1)
+		go func(runner runFunc, done chan bool) {
+			defer wg.Done()
+			runner(client, done)
+		}(runner, done[i])
 	}
 
+	signal.Notify(signals, os.Interrupt, syscall.SIGTERM)
+	go func() {
+		sig := <-signals
+		// restore default handling, so a second signal kills a stuck shutdown
+		signal.Stop(signals)
+		log.Println("Received", sig, "shutting down")
+		close(shutdown)
+		client.Write("QUIT :shutting down")
+		conn.Close()
+	}()
+
 	err = client.Run()
-	if err != nil {
-		for _, ch := range done {
-			ch <- true
+
+	for _, ch := range done {
+		close(ch)
+	}
+	wg.Wait()
+
+	select {
+	case <-shutdown:
+		return
+	default:
+		if err != nil {
+			log.Fatalln(err)
 		}
-		log.Fatalln(err)
 	}
 }
@@ -1,7 +1,9 @@
 package main
 
 import (
+	"flag"
 	"log"
+	"runtime/debug"
 	"sync"
 
 	"gopkg.in/irc.v3"
@@ -9,39 +11,117 @@ import (
 
 type dispatchFunc func(*irc.Client, *irc.Message)
 type dispatchers struct {
-	lock sync.Mutex
-	list []dispatchFunc
+	lock  sync.Mutex
+	names []string
+	list  []dispatchFunc
 }
 
-func (d *dispatchers) Add(f dispatchFunc) {
+func (d *dispatchers) Add(name string, f dispatchFunc) {
 	d.lock.Lock()
 	defer d.lock.Unlock()
 
+	d.names = append(d.names, name)
 	d.list = append(d.list, f)
 }
 
+// only valid after flag.Parse()
+func (d *dispatchers) Enabled() (ret []dispatchFunc) {
+	d.lock.Lock()
+	defer d.lock.Unlock()
+
+	for i, f := range d.list {
+		if isDisabled(d.names[i]) {
+			log.Println("Skipping disabled dispatcher", d.names[i])
+			continue
+		}
+		ret = append(ret, f)
+	}
+
+	return ret
+}
+
 type runFunc func(c *irc.Client, done chan bool)
 type runners struct {
-	lock sync.Mutex
-	list []runFunc
+	lock  sync.Mutex
+	names []string
+	list  []runFunc
 }
 
-func (r *runners) Add(f runFunc) {
+func (r *runners) Add(name string, f runFunc) {
 	r.lock.Lock()
 	defer r.lock.Unlock()
 
+	r.names = append(r.names, name)
 	r.list = append(r.list, f)
 }
 
+// only valid after flag.Parse()
+func (r *runners) Enabled() (ret []runFunc) {
+	r.lock.Lock()
+	defer r.lock.Unlock()
+
+	for i, f := range r.list {
+		if isDisabled(r.names[i]) {
+			log.Println("Skipping disabled runner", r.names[i])
+			continue
+		}
+		ret = append(ret, f)
+	}
+
+	return ret
+}
+
 var (
 	Dispatchers dispatchers
 	Runners     runners
+	disabled    arrayFlags
 )
 
+func isDisabled(name string) bool {
+	return contains(disabled, name)
+}
+
+func contains(list []string, name string) bool {
+	for _, x := range list {
+		if x == name {
+			return true
+		}
+	}
+
+	return false
+}
+
+// a misspelt -disable would otherwise quietly leave the plugin running;
+// only valid after flag.Parse()
+func checkDisabled() {
+	Dispatchers.lock.Lock()
+	defer Dispatchers.lock.Unlock()
+	Runners.lock.Lock()
+	defer Runners.lock.Unlock()
+
+	for _, name := range disabled {
+		if !contains(Dispatchers.names, name) && !contains(Runners.names, name) {
+			log.Fatalln("Unknown dispatcher or runner to disable:", name, "known:", Dispatchers.names, Runners.names)
+		}
+	}
+}
+
+// a panic in any goroutine takes the whole process down, so don't let a
+// single misbehaving dispatcher disconnect the bot
+func safeDispatch(f dispatchFunc, c *irc.Client, m *irc.Message) {
+	defer func() {
+		if r := recover(); r != nil {
+			log.Println("Dispatcher panicked on", m, r, string(debug.Stack()))
+		}
+	}()
+
+	f(c, m)
+}
+
 func handlerFactory(dispatchers []dispatchFunc) func(*irc.Client, *irc.Message) {
 	return func(c *irc.Client, m *irc.Message) {
 		for _, f := range dispatchers {
-			go f(c, m.Copy())
+			go safeDispatch(f, c, m.Copy())
 		}
 	}
 }
@@ -59,6 +139,8 @@ func joiner(c *irc.Client, m *irc.Message) {
 }
 
 func init() {
-	Dispatchers.Add(logger)
-	Dispatchers.Add(joiner)
+	flag.Var(&disabled, "disable", "Dispatcher or runner to skip by name; may be specified multiple times")
+
+	Dispatchers.Add("logger", logger)
+	Dispatchers.Add("joiner", joiner)
 }
@@ -0,0 +1,43 @@
+package main
+
+import (
+	"testing"
+	"time"
+
+	"gopkg.in/irc.v3"
+)
+
+func TestSafeDispatchRecovers(t *testing.T) {
+	panicker := func(*irc.Client, *irc.Message) {
+		panic("boom")
+	}
+
+	// reaching the end of the test is the assertion
+	safeDispatch(panicker, nil, &irc.Message{Command: "PRIVMSG", Params: []string{"#test", "hi"}})
+}
+
+func TestHandlerFactorySurvivesPanics(t *testing.T) {
+	ran := make(chan string, 1)
+
+	handler := handlerFactory([]dispatchFunc{
+		func(*irc.Client, *irc.Message) {
+			panic("boom")
+		},
+		func(_ *irc.Client, m *irc.Message) {
+			ran <- m.Params[1]
+		},
+	})
+
+	for _, text := range []string{"first", "second"} {
+		handler(nil, &irc.Message{Command: "PRIVMSG", Params: []string{"#test", text}})
+
+		select {
+		case got := <-ran:
+			if got != text {
+				t.Errorf("dispatcher got %q, want %q", got, text)
+			}
+		case <-time.After(5 * time.Second):
+			t.Fatalf("dispatcher didn't run for %q after another one panicked", text)
+		}
+	}
+}
@@ -6,56 +6,33 @@ import (
 	"fmt"
 	"log"
 	"sort"
+	"strconv"
 	"strings"
+	"sync"
 	"time"
 
 	"gopkg.in/irc.v3"
 )
 
 var (
-	spaceApiChannels arrayFlags
+	spaceApiChannels          arrayFlags
+	spaceApiCollapseThreshold int
+	spaceApiClosingHour       int
+	spaceApiClosingThreshold  int
+	spaceApiClosingUntil      int
+	spaceApiMaxNames          int
+	spaceApiNoState           arrayFlags
 )
 
+// only the fields we actually use: state for open/closed notices and the
+// closing reminder, people_now_present for presence and head counts; everything
+// else varies too much between SpaceAPI versions and endpoints, and
+// encoding/json skips unknown fields anyway
 type spaceApiResponse struct {
-	API      string `json:"api"`
-	Space    string `json:"space"`
-	Logo     string `json:"logo"`
-	URL      string `json:"url"`
-	Location struct {
-		Lat     float64 `json:"lat"`
-		Lon     float64 `json:"lon"`
-		Address string  `json:"address"`
-	} `json:"location"`
 	State struct {
-		Open    bool   `json:"open"`
+		Open    *bool  `json:"open"` // nil when state or open is missing or null
 		Message string `json:"message"`
-		Icon    struct {
-			Open   string `json:"open"`
-			Closed string `json:"closed"`
-		} `json:"icon"`
 	} `json:"state"`
-	Contact struct {
-		Facebook string `json:"facebook"`
-		Irc      string `json:"irc"`
-		Ml       string `json:"ml"`
-		Twitter  string `json:"twitter"`
-	} `json:"contact"`
-	IssueReportChannels []string `json:"issue_report_channels"`
-	Projects            []string `json:"projects"`
-	Feeds               struct {
-		Blog struct {
-			Type string `json:"type"`
-			URL  string `json:"url"`
-		} `json:"blog"`
-		Calendar struct {
-			Type string `json:"type"`
-			URL  string `json:"url"`
-		} `json:"calendar"`
-		Wiki struct {
-			Type string `json:"type"`
-			URL  string `json:"url"`
-		} `json:"wiki"`
-	} `json:"feeds"`
 	Sensors struct {
 		PeopleNowPresent []struct {
 			Value int      `json:"value"`
@@ -67,18 +44,132 @@ type spaceApiResponse struct {
 func (s *spaceApiResponse) UserListZWS() (ret []string) {
 	for _, room := range s.Sensors.PeopleNowPresent {
 		for _, user := range room.Names {
-			login := user[:1] + "\u200B" + user[1:]
-			ret = append(ret, login)
+			if len(user) == 0 {
+				continue
+			}
+			ret = append(ret, zwsNick(user))
 		}
 	}
 
 	return ret
 }
 
+// head count from the people sensor, taking value but never less than the
+// number of names listed; ok is false when the endpoint has no such sensor
+func (s *spaceApiResponse) PeopleCount() (count int, ok bool) {
+	for _, room := range s.Sensors.PeopleNowPresent {
+		if room.Value > len(room.Names) {
+			count += room.Value
+		} else {
+			count += len(room.Names)
+		}
+	}
+
+	return count, len(s.Sensors.PeopleNowPresent) > 0
+}
+
 type spaceApiClient struct {
-	ircChannel string
-	apiUrl     string
-	users      []string
+	ircChannel        string
+	apiUrl            string
+	users             []string
+	collapseThreshold int
+	open              bool
+	stateKnown        bool
+	closingHour       int
+	closingThreshold  int
+	closingUntil      int
+	lastReminder      string
+	maxNames          int
+	noState           bool
+}
+
+// collapse long user lists into a head count, so mass arrivals don't flood the channel
+func (s *spaceApiClient) formatUsers(users []string) string {
+	if s.collapseThreshold > 0 && len(users) > s.collapseThreshold {
+		return fmt.Sprint(len(users), " people")
+	}
+
+	return fmt.Sprint(users)
+}
+
+// show at most maxNames of the people already there, followed by "+N more"
+func (s *spaceApiClient) capUsers(users []string) string {
+	if s.maxNames > 0 && len(users) > s.maxNames {
+		return fmt.Sprint(users[:s.maxNames], " +", len(users)-s.maxNames, " more")
+	}
+
+	return fmt.Sprint(users)
+}
+
+// returns a notice text when state.open flipped since the previous poll; the
+// first poll only records the state, so restarts don't announce anything, and
+// polls that don't report the state leave the last known one alone
+func (s *spaceApiClient) stateChange(response spaceApiResponse) (text string) {
+	if s.noState || response.State.Open == nil {
+		return ""
+	}
+
+	if s.stateKnown && *response.State.Open != s.open {
+		if *response.State.Open {
+			text = "space is now open"
+		} else {
+			text = "space is now closed"
+		}
+
+		if len(response.State.Message) > 0 {
+			text += ": " + response.State.Message
+		}
+	}
+
+	s.open = *response.State.Open
+	s.stateKnown = true
+
+	return text
+}
+
+// the late window runs from closingHour until closingUntil, wrapping past
+// midnight if needed; returns the date the current window started, or "" when
+// now is outside of it
+func (s *spaceApiClient) closingWindow(now time.Time) string {
+	hour := now.Hour()
+
+	if s.closingHour <= s.closingUntil {
+		if hour >= s.closingHour && hour < s.closingUntil {
+			return now.Format("2006-01-02")
+		}
+		return ""
+	}
+
+	if hour >= s.closingHour {
+		return now.Format("2006-01-02")
+	}
+
+	if hour < s.closingUntil {
+		return now.AddDate(0, 0, -1).Format("2006-01-02")
+	}
+
+	return ""
+}
+
+// reminds whoever is left to close up when the space is still open late with
+// few people around; at most once per late window
+func (s *spaceApiClient) closingReminder(response spaceApiResponse, now time.Time) string {
+	if s.closingHour < 0 || response.State.Open == nil || !*response.State.Open {
+		return ""
+	}
+
+	present, ok := response.PeopleCount()
+	if !ok || present > s.closingThreshold {
+		return ""
+	}
+
+	window := s.closingWindow(now)
+	if len(window) == 0 || s.lastReminder == window {
+		return ""
+	}
+	s.lastReminder = window
+
+	return "it's getting late, last person please close up the space"
 }
 
 func (s *spaceApiClient) Run(c *irc.Client, done chan bool) {
@@ -97,24 +188,34 @@ func (s *spaceApiClient) Run(c *irc.Client, done chan bool) {
 				break
 			}
 
+			if stateText := s.stateChange(response); len(stateText) > 0 {
+				log.Println(stateText)
+				c.Write(fmt.Sprintf("NOTICE %s :%s\n", s.ircChannel, stateText))
+			}
+
 			current := response.UserListZWS()
 
+			if reminder := s.closingReminder(response, time.Now()); len(reminder) > 0 {
+				log.Println(reminder)
+				c.Write(fmt.Sprintf("NOTICE %s :%s\n", s.ircChannel, reminder))
+			}
+
 			arrived := listSubtract(current, s.users)
 			left := listSubtract(s.users, current)
 			alsoThere := listSubtract(s.users, left)
 			sort.Strings(alsoThere)
 
 			if len(arrived) > 0 {
-				diffText = fmt.Sprint(" arrived: ", arrived)
+				diffText = fmt.Sprint(" arrived: ", s.formatUsers(arrived))
 			}
 
 			if len(left) > 0 {
-				diffText += fmt.Sprint(" left: ", left)
+				diffText += fmt.Sprint(" left: ", s.formatUsers(left))
 			}
 
 			if len(diffText) > 0 {
 				if len(alsoThere) > 0 {
-					diffText += fmt.Sprint(" also there: ", alsoThere)
+					diffText += fmt.Sprint(" also there: ", s.capUsers(alsoThere))
 				}
 
 				msg := fmt.Sprintf("NOTICE %s :%s\n", s.ircChannel, diffText)
@@ -131,42 +232,96 @@ func (s *spaceApiClient) currentState() (at spaceApiResponse, err error) {
 
 	data, err := httpGet(s.apiUrl)
 	if err != nil {
-		return values, fmt.Errorf("Unable to access spaceApi:", err)
+		return values, fmt.Errorf("Unable to access spaceApi: %w", err)
 	}
 
 	err = json.Unmarshal(data, &values)
 	if err != nil {
-		return values, fmt.Errorf("Unable to decode spaceApi response:", err)
+		return values, fmt.Errorf("Unable to decode spaceApi response: %w", err)
 	}
 
 	return values, nil
 }
 
+// parses an ircChannel,spaceapi[,collapseThreshold] mapping into a client,
+// filling in everything else from the global flags
+func newSpaceApiClient(mapping string) (*spaceApiClient, error) {
+	args := strings.Split(mapping, ",")
+	if len(args) != 2 && len(args) != 3 {
+		return nil, fmt.Errorf("Wrong spaceApi channel mapping format: %q", mapping)
+	}
+
+	collapseThreshold := spaceApiCollapseThreshold
+	if len(args) == 3 {
+		threshold, err := strconv.Atoi(args[2])
+		if err != nil || threshold < 0 {
+			return nil, fmt.Errorf("Wrong spaceApi collapse threshold in %q: not a non-negative number", mapping)
+		}
+		collapseThreshold = threshold
+	}
+
+	s := &spaceApiClient{
+		ircChannel:        args[0],
+		apiUrl:            args[1],
+		collapseThreshold: collapseThreshold,
+		closingHour:       spaceApiClosingHour,
+		closingThreshold:  spaceApiClosingThreshold,
+		closingUntil:      spaceApiClosingUntil,
+		maxNames:          spaceApiMaxNames,
+	}
+
+	for _, url := range spaceApiNoState {
+		if url == s.apiUrl {
+			s.noState = true
+		}
+	}
+
+	return s, nil
+}
+
 func spaceApiRunWrapper(c *irc.Client, done chan bool) {
+	var wg sync.WaitGroup
 	spaceApiDone := make([]chan bool, len(spaceApiChannels))
 
-	for i, ch := range spaceApiChannels {
-		args := strings.Split(ch, ",")
-		if len(args) != 2 {
-			log.Fatalln("Wrong spaceApi channel mapping format", ch, args)
+	if spaceApiClosingHour != -1 {
+		if spaceApiClosingHour < 0 || spaceApiClosingHour > 23 || spaceApiClosingUntil < 0 || spaceApiClosingUntil > 23 {
+			log.Fatalln("Wrong spaceApi closing hours, both must be within 0-23", spaceApiClosingHour, spaceApiClosingUntil)
 		}
 
-		s := spaceApiClient{
-			ircChannel: args[0],
-			apiUrl:     args[1],
+		if spaceApiClosingHour == spaceApiClosingUntil {
+			log.Fatalln("Wrong spaceApi closing hours, the reminder window would be empty", spaceApiClosingHour, spaceApiClosingUntil)
+		}
+	}
+
+	for i, ch := range spaceApiChannels {
+		s, err := newSpaceApiClient(ch)
+		if err != nil {
+			log.Fatalln(err)
 		}
 
-		go s.Run(c, spaceApiDone[i])
+		spaceApiDone[i] = make(chan bool)
+		wg.Add(1)
+		go func(done chan bool) {
+			defer wg.Done()
+			s.Run(c, done)
+		}(spaceApiDone[i])
 	}
 
 	<-done
 	for _, ch := range spaceApiDone {
-		ch <- true
+		close(ch)
 	}
+	wg.Wait()
 }
 
 func init() {
-	flag.Var(&spaceApiChannels, "spaceapi.channels", "ircChannel,spaceapi mapping; may be specified multiple times")
+	flag.Var(&spaceApiChannels, "spaceapi.channels", "ircChannel,spaceapi[,collapseThreshold] mapping; may be specified multiple times")
+	flag.IntVar(&spaceApiCollapseThreshold, "spaceapi.collapseThreshold", 0, "Report only a head count when more than this many people arrive or leave at once; 0 disables. Default for mappings without their own threshold")
+	flag.IntVar(&spaceApiClosingHour, "spaceapi.closingHour", -1, "Hour (0-23) after which to remind people to close up an open space; -1 disables")
+	flag.IntVar(&spaceApiClosingUntil, "spaceapi.closingUntil", 6, "Hour (0-23) at which the closing reminder window ends; may be past midnight")
+	flag.IntVar(&spaceApiClosingThreshold, "spaceapi.closingThreshold", 1, "Send the closing reminder only when at most this many people are present")
+	flag.IntVar(&spaceApiMaxNames, "spaceapi.maxNames", 0, "List at most this many people already present, followed by \"+N more\"; 0 lists everyone")
+	flag.Var(&spaceApiNoState, "spaceapi.noState", "spaceapi URL whose open/closed state changes shouldn't be announced; may be specified multiple times")
 
-	Runners.Add(spaceApiRunWrapper)
+	Runners.Add("spaceapi", spaceApiRunWrapper)
 }
@@ -0,0 +1,261 @@
+package main
+
+import (
+	"encoding/json"
+	"reflect"
+	"testing"
+	"time"
+)
+
+func TestSpaceApiDecode(t *testing.T) {
+	tests := []struct {
+		name    string
+		payload string
+		want    []string
+	}{
+		{
+			name:    "minimal",
+			payload: `{"space": "hswaw", "state": {"open": true}}`,
+			want:    nil,
+		},
+		{
+			name: "minimal with people",
+			payload: `{"api_compatibility": ["14"], "space": "hswaw", "state": {"open": true},
+				"sensors": {"people_now_present": [{"value": 2, "names": ["alice", "bob"]}]}}`,
+			want: []string{"a\u200Blice", "b\u200Bob"},
+		},
+		{
+			name: "extended, with unused sections in unexpected shapes",
+			payload: `{"api": "0.13", "space": "hswaw", "logo": "https://example.org/logo.png",
+				"location": {"lat": "52.2", "lon": "21.0", "address": ["somewhere"]},
+				"contact": {"twitter": {"handle": "@hswaw"}, "irc": "ircs://irc.libera.chat/hswaw"},
+				"projects": "none", "feeds": [],
+				"state": {"open": false, "message": "closed for the night", "icon": {"open": "o", "closed": "c"}},
+				"sensors": {"people_now_present": [
+					{"value": 1, "names": ["alice"], "location": "hackroom"},
+					{"value": 1, "names": ["Łukasz"], "location": "workshop"}
+				]}}`,
+			want: []string{"a\u200Blice", "Ł\u200Bukasz"},
+		},
+		{
+			name:    "empty names are skipped",
+			payload: `{"sensors": {"people_now_present": [{"value": 2, "names": ["", "bob"]}]}}`,
+			want:    []string{"b\u200Bob"},
+		},
+	}
+
+	for _, tt := range tests {
+		var resp spaceApiResponse
+
+		if err := json.Unmarshal([]byte(tt.payload), &resp); err != nil {
+			t.Errorf("%s: unexpected decode error: %v", tt.name, err)
+			continue
+		}
+
+		if got := resp.UserListZWS(); !reflect.DeepEqual(got, tt.want) {
+			t.Errorf("%s: UserListZWS() = %q, want %q", tt.name, got, tt.want)
+		}
+	}
+}
+
+func TestSpaceApiStateChange(t *testing.T) {
+	polls := []struct {
+		payload string
+		want    string
+	}{
+		{`{"state": {"open": true}}`, ""}, // first poll only records the state
+		{`{"state": {"open": true}}`, ""},
+		{`{"state": {"open": false, "message": "see you tomorrow"}}`, "space is now closed: see you tomorrow"},
+		{`{"state": {"open": null}}`, ""},
+		{`{"sensors": {}}`, ""},
+		{`{"state": {"open": false}}`, ""},
+		{`{"state": {"open": true}}`, "space is now open"},
+	}
+
+	s := spaceApiClient{}
+
+	for i, poll := range polls {
+		var resp spaceApiResponse
+
+		if err := json.Unmarshal([]byte(poll.payload), &resp); err != nil {
+			t.Fatalf("poll %d: unexpected decode error: %v", i, err)
+		}
+
+		if got := s.stateChange(resp); got != poll.want {
+			t.Errorf("poll %d: stateChange() = %q, want %q", i, got, poll.want)
+		}
+	}
+}
+
+func TestSpaceApiStateChangeDisabled(t *testing.T) {
+	s := spaceApiClient{noState: true}
+
+	for _, payload := range []string{`{"state": {"open": true}}`, `{"state": {"open": false}}`} {
+		var resp spaceApiResponse
+
+		if err := json.Unmarshal([]byte(payload), &resp); err != nil {
+			t.Fatalf("unexpected decode error: %v", err)
+		}
+
+		if got := s.stateChange(resp); got != "" {
+			t.Errorf("stateChange() = %q with noState set, want nothing", got)
+		}
+	}
+}
+
+func decodeSpaceApi(t *testing.T, payload string) spaceApiResponse {
+	var resp spaceApiResponse
+
+	if err := json.Unmarshal([]byte(payload), &resp); err != nil {
+		t.Fatalf("unexpected decode error: %v", err)
+	}
+
+	return resp
+}
+
+func TestSpaceApiClosingReminder(t *testing.T) {
+	const (
+		oneName  = `{"state": {"open": true}, "sensors": {"people_now_present": [{"value": 1, "names": ["alice"]}]}}`
+		oneValue = `{"state": {"open": true}, "sensors": {"people_now_present": [{"value": 1}]}}`
+	)
+
+	tests := []struct {
+		name    string
+		hour    int
+		until   int
+		payload string
+		at      int
+		want    bool
+	}{
+		{"disabled", -1, 6, oneName, 23, false},
+		{"before the window", 22, 6, oneName, 21, false},
+		{"evening", 22, 6, oneName, 23, true},
+		{"past midnight", 22, 6, oneName, 1, true},
+		{"after the cutoff", 22, 6, oneName, 6, false},
+		{"afternoon", 22, 6, oneName, 15, false},
+		{"non-wrapping window", 0, 6, oneName, 3, true},
+		{"outside non-wrapping window", 0, 6, oneName, 12, false},
+		{"count from value only", 22, 6, oneValue, 23, true},
+		{"closed", 22, 6, `{"state": {"open": false}, "sensors": {"people_now_present": [{"value": 1}]}}`, 23, false},
+		{"state unknown", 22, 6, `{"sensors": {"people_now_present": [{"value": 1}]}}`, 23, false},
+		{"no people sensor", 22, 6, `{"state": {"open": true}}`, 23, false},
+		{"too many by value", 22, 6, `{"state": {"open": true}, "sensors": {"people_now_present": [{"value": 5}]}}`, 23, false},
+		{"too many by names", 22, 6, `{"state": {"open": true}, "sensors": {"people_now_present": [{"value": 0, "names": ["a", "b", "c"]}]}}`, 23, false},
+		{"too many across rooms", 22, 6, `{"state": {"open": true}, "sensors": {"people_now_present": [{"value": 1}, {"value": 1}]}}`, 23, false},
+	}
+
+	for _, tt := range tests {
+		s := spaceApiClient{closingHour: tt.hour, closingUntil: tt.until, closingThreshold: 1}
+		now := time.Date(2026, 10, 16, tt.at, 30, 0, 0, time.Local)
+
+		got := s.closingReminder(decodeSpaceApi(t, tt.payload), now)
+		if (len(got) > 0) != tt.want {
+			t.Errorf("%s: closingReminder() = %q, want reminder: %v", tt.name, got, tt.want)
+		}
+	}
+}
+
+func TestSpaceApiClosingReminderOncePerWindow(t *testing.T) {
+	const payload = `{"state": {"open": true}, "sensors": {"people_now_present": [{"value": 1}]}}`
+
+	polls := []struct {
+		at   time.Time
+		want bool
+	}{
+		{time.Date(2026, 10, 16, 22, 30, 0, 0, time.Local), true},
+		{time.Date(2026, 10, 16, 23, 30, 0, 0, time.Local), false},
+		{time.Date(2026, 10, 17, 1, 0, 0, 0, time.Local), false}, // same night
+		{time.Date(2026, 10, 17, 22, 30, 0, 0, time.Local), true},
+		{time.Date(2026, 10, 18, 2, 0, 0, 0, time.Local), false},
+		{time.Date(2026, 10, 19, 2, 0, 0, 0, time.Local), true}, // missed the evening, still reminded that night
+	}
+
+	s := spaceApiClient{closingHour: 22, closingUntil: 6, closingThreshold: 1}
+	resp := decodeSpaceApi(t, payload)
+
+	for i, poll := range polls {
+		got := s.closingReminder(resp, poll.at)
+		if (len(got) > 0) != poll.want {
+			t.Errorf("poll %d at %s: closingReminder() = %q, want reminder: %v", i, poll.at, got, poll.want)
+		}
+	}
+}
+
+func TestSpaceApiFormatUsers(t *testing.T) {
+	tests := []struct {
+		threshold int
+		users     []string
+		want      string
+	}{
+		{0, []string{"a", "b", "c", "d", "e"}, "[a b c d e]"},
+		{3, []string{"a"}, "[a]"},
+		{3, []string{"a", "b", "c"}, "[a b c]"},
+		{3, []string{"a", "b", "c", "d"}, "4 people"},
+	}
+
+	for _, tt := range tests {
+		s := spaceApiClient{collapseThreshold: tt.threshold}
+
+		if got := s.formatUsers(tt.users); got != tt.want {
+			t.Errorf("formatUsers(%q) with threshold %d = %q, want %q", tt.users, tt.threshold, got, tt.want)
+		}
+	}
+}
+
+func TestNewSpaceApiClient(t *testing.T) {
+	defer func(threshold int) { spaceApiCollapseThreshold = threshold }(spaceApiCollapseThreshold)
+	spaceApiCollapseThreshold = 5
+
+	tests := []struct {
+		mapping   string
+		wantErr   bool
+		threshold int
+	}{
+		{"#hswaw,https://example.org/spaceapi", false, 5},
+		{"#hswaw,https://example.org/spaceapi,3", false, 3},
+		{"#hswaw,https://example.org/spaceapi,0", false, 0},
+		{"#hswaw,https://example.org/spaceapi,many", true, 0},
+		{"#hswaw,https://example.org/spaceapi,-1", true, 0},
+		{"#hswaw", true, 0},
+		{"#hswaw,https://example.org/spaceapi,3,4", true, 0},
+	}
+
+	for _, tt := range tests {
+		s, err := newSpaceApiClient(tt.mapping)
+		if (err != nil) != tt.wantErr {
+			t.Errorf("newSpaceApiClient(%q): err = %v, want error: %v", tt.mapping, err, tt.wantErr)
+			continue
+		}
+		if err != nil {
+			continue
+		}
+
+		if s.ircChannel != "#hswaw" || s.apiUrl != "https://example.org/spaceapi" {
+			t.Errorf("newSpaceApiClient(%q) = %q, %q, want #hswaw, https://example.org/spaceapi", tt.mapping, s.ircChannel, s.apiUrl)
+		}
+		if s.collapseThreshold != tt.threshold {
+			t.Errorf("newSpaceApiClient(%q) threshold = %d, want %d", tt.mapping, s.collapseThreshold, tt.threshold)
+		}
+	}
+}
+
+func TestSpaceApiCapUsers(t *testing.T) {
+	tests := []struct {
+		maxNames int
+		users    []string
+		want     string
+	}{
+		{0, []string{"a", "b", "c", "d", "e"}, "[a b c d e]"},
+		{2, []string{"a", "b"}, "[a b]"},
+		{2, []string{"a", "b", "c"}, "[a b] +1 more"},
+		{2, []string{"a", "b", "c", "d", "e"}, "[a b] +3 more"},
+	}
+
+	for _, tt := range tests {
+		s := spaceApiClient{maxNames: tt.maxNames}
+
+		if got := s.capUsers(tt.users); got != tt.want {
+			t.Errorf("capUsers(%q) with maxNames %d = %q, want %q", tt.users, tt.maxNames, got, tt.want)
+		}
+	}
+}